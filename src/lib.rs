@@ -29,24 +29,260 @@
 //! push_html(&mut buf, parser);
 //! assert_eq!(buf.trim_end(), r#"<h2 id="heading-id">Heading</h2>"#);
 //! ```
+//!
+//! ## Auto-generated IDs
+//!
+//! When a heading has no explicit `{#id}`, [`HeadingId::with_autoslug`] derives one from
+//! the heading's own text, the way rustdoc's `IdMap` does. Slugs that collide are
+//! disambiguated with a `-1`, `-2`, ... suffix.
+//! ```
+//! use pulldown_cmark::Parser;
+//! use pulldown_cmark::html::push_html;
+//! use markdown_heading_id::HeadingId;
+//!
+//! let parser = Parser::new("## Heading\n\n## Heading");
+//! let parser = HeadingId::with_autoslug(parser);
+//! let mut buf = String::new();
+//! push_html(&mut buf, parser);
+//! assert_eq!(buf.trim_end(), "<h2 id=\"heading\">Heading</h2>\n<h2 id=\"heading-1\">Heading</h2>");
+//! ```
+//!
+//! ## Table of contents
+//!
+//! `HeadingId` records every heading it sees as a [`TocEntry`]. After (or while)
+//! driving the iterator, [`HeadingId::toc`] returns them in document order, and
+//! [`render_toc`] turns that list into a nested `<ul>`/`<li>` fragment suitable for
+//! sidebar navigation.
+//! ```
+//! use pulldown_cmark::Parser;
+//! use pulldown_cmark::html::push_html;
+//! use markdown_heading_id::{HeadingId, render_toc};
+//!
+//! let parser = Parser::new("## Heading {#heading-id}");
+//! let mut parser = HeadingId::new(parser);
+//! let mut buf = String::new();
+//! push_html(&mut buf, &mut parser);
+//! assert_eq!(render_toc(parser.toc()),
+//!     "<ul>\n<li><a href=\"#heading-id\">Heading</a></li>\n</ul>\n");
+//! ```
+//!
+//! ## Classes and key/value attributes
+//!
+//! The attribute block also accepts `.class` tokens and `key=value` pairs, e.g.
+//! `## Heading {#id .foo .bar key=val}` is converted into
+//! `<h2 id="id" class="foo bar" key="val">Heading</h2>`.
+//!
+//! ## Composing with other event consumers
+//!
+//! By default a heading is collapsed into a single `Event::Html`. Built with
+//! [`HeadingId::with_event_replay`], `HeadingId` instead emits the opening and
+//! closing tags as their own `Event::Html`s and replays the buffered inner
+//! events in between, so other filters downstream of `HeadingId` still see them.
 
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use pulldown_cmark::{Event, Tag};
 use pulldown_cmark::escape::{StrWrite, escape_html, escape_href};
 use pulldown_cmark::html::push_html;
 
-fn find_custom_id(s: &str) -> (&str, Option<&str>) {
-    let (before_brace, after_brace) = match s.find("{#") {
-        Some(pos) => (&s[..pos], &s[pos+2..]),
-        None => return (s, None),
-    };
+/// Attributes parsed out of a trailing `{...}` attribute block.
+///
+/// Mirrors the extended attribute syntax of `{#id .class1 .class2 key=val}`:
+/// at most one `#id`, any number of `.class` tokens, and any number of
+/// `key=value` pairs.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct HeadingAttrs {
+    id: Option<String>,
+    classes: Vec<String>,
+    pairs: Vec<(String, String)>,
+}
+
+impl HeadingAttrs {
+    fn is_empty(&self) -> bool {
+        self.id.is_none() && self.classes.is_empty() && self.pairs.is_empty()
+    }
+}
+
+/// Splits `s` into the text before a trailing `{...}` attribute block and the
+/// attributes found inside it.
+///
+/// Returns `None` unless the block is anchored at the end of `s` (its `}` is
+/// the last non-whitespace character) *and* it actually yields an id, class,
+/// or key/value pair. Otherwise a bare `{...}` occurring in ordinary heading
+/// text, such as `## Set {x} to y`, would be mistaken for an attribute block
+/// and silently swallowed.
+fn find_attrs(s: &str) -> Option<(&str, HeadingAttrs)> {
+    let trimmed = s.trim_end();
+    if !trimmed.ends_with('}') {
+        return None;
+    }
+
+    let open = trimmed.rfind('{')?;
+    let inner_brace = &trimmed[open+1..trimmed.len()-1];
+
+    let mut attrs = HeadingAttrs::default();
+
+    for token in inner_brace.split_whitespace() {
+        if let Some(id) = token.strip_prefix('#') {
+            attrs.id = Some(id.to_string());
+        } else if let Some(class) = token.strip_prefix('.') {
+            attrs.classes.push(class.to_string());
+        } else if let Some(pos) = token.find('=') {
+            let key = &token[..pos];
+            let value = token[pos+1..].trim_matches('"');
+            attrs.pairs.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    if attrs.is_empty() {
+        return None;
+    }
+
+    Some((s[..open].trim_end(), attrs))
+}
+
+/// Finds the longest run of trailing `Event::Text` events in `events` and
+/// joins their contents.
+///
+/// The `{#id ...}` block can land after an inline node (`` `code` ``, `*em*`,
+/// ...) with no text in between, or be split across adjacent text events;
+/// joining the whole trailing run before searching for the block handles
+/// both without assuming it is confined to a single `Event::Text`.
+fn trailing_text(events: &[Event]) -> Option<(usize, String)> {
+    let mut start = events.len();
+
+    while start > 0 && matches!(events[start - 1], Event::Text(_)) {
+        start -= 1;
+    }
+
+    if start == events.len() {
+        return None;
+    }
+
+    let mut joined = String::new();
+    for event in &events[start..] {
+        if let Event::Text(text) = event {
+            joined.push_str(text);
+        }
+    }
+
+    Some((start, joined))
+}
+
+/// Concatenates the text of a heading's buffered events.
+///
+/// Only `Event::Text` and `Event::Code` carry characters; the other events
+/// (emphasis markers, links, ...) are structural and contribute nothing.
+fn heading_text(events: &[Event]) -> String {
+    let mut text = String::new();
+
+    for event in events {
+        match event {
+            Event::Text(s) => text.push_str(s),
+            Event::Code(s) => text.push_str(s),
+            _ => {},
+        }
+    }
+
+    text
+}
+
+/// Renders a heading's buffered events to HTML for use as a [`TocEntry`]'s
+/// text, dropping any link's `Start`/`End` wrapper so only its contents
+/// remain.
+///
+/// [`render_toc`] wraps every entry's text in its own `<a href="#...">`;
+/// replaying a link event pair unchanged would nest an `<a>` inside it,
+/// which browsers mangle.
+fn toc_text(events: &[Event]) -> String {
+    let unwrapped = events.iter()
+        .filter(|event| !matches!(event, Event::Start(Tag::Link(..)) | Event::End(Tag::Link(..))))
+        .cloned();
+
+    let mut html = String::new();
+    push_html(&mut html, unwrapped);
+    html
+}
+
+/// Derives a slug from a heading's plain text.
+///
+/// The text is lowercased, runs of whitespace are collapsed into a single
+/// `-`, and every character that is not in `[a-z0-9-_]` is dropped.
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    let mut in_whitespace = false;
+
+    for c in text.to_lowercase().chars() {
+        if c.is_whitespace() {
+            in_whitespace = true;
+            continue;
+        }
+
+        if in_whitespace && !slug.is_empty() {
+            slug.push('-');
+        }
+        in_whitespace = false;
+
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            slug.push(c);
+        }
+    }
 
-    let (inner_brace, _after_brace) = match after_brace.find('}') {
-        Some(pos) => (&after_brace[..pos], &after_brace[pos+1..]),
-        None => return (s, None),
-    };
+    slug
+}
 
-    (before_brace.trim_end(), Some(inner_brace))
+/// One heading captured while driving a [`HeadingId`].
+///
+/// `text` is the already-rendered inner HTML of the heading (inline code,
+/// emphasis, ... included), ready to be dropped into an `<a>` tag. Any link
+/// the heading itself contains is unwrapped down to its contents, since
+/// [`render_toc`] already wraps `text` in a link of its own and a nested
+/// `<a>` would be invalid HTML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    pub level: u32,
+    pub id: String,
+    pub text: String,
+}
+
+/// Renders a table of contents as a nested `<ul>`/`<li>` fragment.
+///
+/// A new `<ul>` is opened whenever an entry is deeper than the previous one,
+/// and closed again once an entry shallower than that sublist is reached; an
+/// equal-level entry only closes the previous `<li>`.
+pub fn render_toc(entries: &[TocEntry]) -> String {
+    let mut html = String::new();
+    let mut levels: Vec<u32> = Vec::new();
+
+    for entry in entries {
+        loop {
+            match levels.last() {
+                Some(&top) if top == entry.level => {
+                    html.push_str("</li>\n");
+                    break;
+                },
+                Some(&top) if top > entry.level => {
+                    html.push_str("</li>\n</ul>\n");
+                    levels.pop();
+                },
+                _ => {
+                    html.push_str("<ul>\n");
+                    levels.push(entry.level);
+                    break;
+                },
+            }
+        }
+
+        write!(&mut html, "<li><a href=\"#").unwrap();
+        escape_href(&mut html, &entry.id).unwrap();
+        write!(&mut html, "\">{}</a>", entry.text).unwrap();
+    }
+
+    for _ in levels {
+        html.push_str("</li>\n</ul>\n");
+    }
+
+    html
 }
 
 /// Converts headings with ID into HTML
@@ -57,8 +293,32 @@ fn find_custom_id(s: &str) -> (&str, Option<&str>) {
 /// `Event`s between a start of `Tag::Heading` and end thereof are converted into one
 /// `Event::HTML`.
 /// It buffers those events because the heading id is positioned at the tail of heading line.
+///
+/// When built with [`HeadingId::with_autoslug`], a heading without an explicit `{#id}`
+/// is assigned an ID derived from its own text instead of being left without one.
+/// A map of slug to occurrence count is kept so that repeated headings still get
+/// distinct IDs (`heading`, `heading-1`, `heading-2`, ...), mirroring rustdoc's `IdMap`.
+///
+/// [`HeadingId::with_offset`] shifts the level that is rendered (e.g. an `h1` becomes
+/// an `h3`), clamped to the `1..=6` range, for embedding a document inside a larger
+/// page. The offset only affects what is written out; the heading is still matched
+/// against its original level coming from the parser.
+///
+/// By default a heading is collapsed into a single `Event::Html`, which hides its
+/// inner events from the rest of the pipeline. [`HeadingId::with_event_replay`]
+/// switches to a non-collapsing mode: the opening `<hN id="...">` and closing
+/// `</hN>` are still emitted as `Event::Html`, but the buffered inner events
+/// (`Event::Text`, `Event::Code`, `Event::Start(Tag::Link)`, ...) are replayed
+/// individually in between, with the `{#id ...}` block stripped from the trailing
+/// text. This keeps `HeadingId` composable with downstream event consumers.
 pub struct HeadingId<'a, P> {
     parser: P,
+    autoslug: bool,
+    slug_counts: HashMap<String, usize>,
+    toc: Vec<TocEntry>,
+    offset: i32,
+    collapse: bool,
+    pending: VecDeque<Event<'a>>,
     _marker: PhantomData<&'a P>,
 }
 
@@ -69,10 +329,78 @@ where
     pub fn new(parser: P) -> Self {
         Self {
             parser: parser,
+            autoslug: false,
+            slug_counts: HashMap::new(),
+            toc: Vec::new(),
+            offset: 0,
+            collapse: true,
+            pending: VecDeque::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Wraps `parser`, deriving an ID from the heading text whenever no
+    /// explicit `{#id}` is present.
+    pub fn with_autoslug(parser: P) -> Self {
+        Self {
+            parser: parser,
+            autoslug: true,
+            slug_counts: HashMap::new(),
+            toc: Vec::new(),
+            offset: 0,
+            collapse: true,
+            pending: VecDeque::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Shifts the rendered heading level by `offset`, clamping to `1..=6`.
+    ///
+    /// Useful when a Markdown fragment is embedded inside a larger page and
+    /// its `#` headings need to be demoted, e.g. `offset = 2` turns an `h1`
+    /// into an `h3`.
+    pub fn with_offset(mut self, offset: i32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Replays a heading's inner events instead of collapsing them into one
+    /// `Event::Html`, so that downstream event consumers still see them.
+    pub fn with_event_replay(mut self) -> Self {
+        self.collapse = false;
+        self
+    }
+
+    /// Returns the headings captured so far, in document order.
+    pub fn toc(&self) -> &[TocEntry] {
+        &self.toc
+    }
+
+    /// Applies `self.offset` to `level`, clamped to the valid `1..=6` range.
+    fn rendered_level(&self, level: u32) -> u32 {
+        (level as i32 + self.offset).clamp(1, 6) as u32
+    }
+
+    /// Returns a unique ID for `slug`, appending a `-{n}` suffix on collision.
+    fn dedup_slug(&mut self, slug: String) -> String {
+        match self.slug_counts.get(&slug).copied() {
+            None => {
+                self.slug_counts.insert(slug.clone(), 1);
+                slug
+            },
+            Some(mut n) => {
+                let mut id = format!("{}-{}", slug, n);
+                while self.slug_counts.contains_key(&id) {
+                    n += 1;
+                    id = format!("{}-{}", slug, n);
+                }
+                self.slug_counts.insert(slug, n + 1);
+                self.slug_counts.insert(id.clone(), 1);
+                id
+            },
+        }
+    }
+
     fn convert_heading(&mut self, level: u32) -> Event<'a> {
         // Read events until the end of heading comes.
         let mut buffer = Vec::new();
@@ -85,40 +413,85 @@ where
             buffer.push(event.clone());
         }
 
-        // Convert the events into an HTML
-        let mut html = String::new();
-        let mut start_tag = String::new();
+        let rendered_level = self.rendered_level(level);
 
-        if let Some((last, events)) = buffer.split_last() {
-            push_html(&mut html, events.iter().cloned());
-
-            match last {
-                Event::Text(text) => {
-                    let (text, id) = find_custom_id(text);
-                    escape_html(&mut html, text).unwrap();
-
-                    if let Some(id) = id {
-                        write!(&mut start_tag, "<h{} id=\"", level).unwrap();
-                        escape_href(&mut start_tag, id).unwrap();
-                        write!(&mut start_tag, "\">").unwrap();
-                    } else {
-                        write!(&mut start_tag, "<h{}>", level).unwrap();
-                    }
-                },
-                event => {
-                    push_html(&mut html, vec![event.clone()].into_iter());
-                },
+        // Strip the `{#id ...}` block from the trailing run of text events,
+        // keeping the rest of the buffer as-is so it can be replayed
+        // event-by-event.
+        let mut id = None;
+        let mut attrs = HeadingAttrs::default();
+        let mut inner_events = buffer.clone();
+
+        if let Some((start, joined)) = trailing_text(&inner_events) {
+            if let Some((stripped, text_attrs)) = find_attrs(&joined) {
+                id = text_attrs.id.clone();
+                attrs = text_attrs;
+                inner_events.truncate(start);
+                inner_events.push(Event::Text(stripped.to_string().into()));
             }
-        } else {
-            write!(&mut start_tag, "<h{}>", level).unwrap();
         }
 
-        writeln!(&mut html, "</h{}>", level).unwrap();
+        if id.is_none() && self.autoslug {
+            let slug = slugify(&heading_text(&inner_events));
+            if !slug.is_empty() {
+                id = Some(slug);
+            }
+        }
+
+        // Only run ids through the dedup registry when autoslug is opted
+        // into: it's the feature that can generate a colliding id in the
+        // first place, so that's the only path that should be able to
+        // rewrite an id the document wrote by hand. Outside of it, explicit
+        // `{#id}` values must pass through unchanged, as they always have.
+        if self.autoslug {
+            id = id.map(|id| self.dedup_slug(id));
+        }
+
+        let mut start_tag = String::new();
+        write!(&mut start_tag, "<h{}", rendered_level).unwrap();
+
+        if let Some(id) = &id {
+            write!(&mut start_tag, " id=\"").unwrap();
+            escape_href(&mut start_tag, id).unwrap();
+            write!(&mut start_tag, "\"").unwrap();
+        }
+
+        if !attrs.classes.is_empty() {
+            write!(&mut start_tag, " class=\"").unwrap();
+            escape_html(&mut start_tag, &attrs.classes.join(" ")).unwrap();
+            write!(&mut start_tag, "\"").unwrap();
+        }
+
+        for (key, value) in &attrs.pairs {
+            write!(&mut start_tag, " ").unwrap();
+            escape_html(&mut start_tag, key).unwrap();
+            write!(&mut start_tag, "=\"").unwrap();
+            escape_html(&mut start_tag, value).unwrap();
+            write!(&mut start_tag, "\"").unwrap();
+        }
 
-        start_tag += &html;
-        let html = start_tag;
-        
-        Event::Html(html.into())
+        write!(&mut start_tag, ">").unwrap();
+
+        let mut inner_html = String::new();
+        push_html(&mut inner_html, inner_events.iter().cloned());
+
+        self.toc.push(TocEntry {
+            level: rendered_level,
+            id: id.unwrap_or_default(),
+            text: toc_text(&inner_events),
+        });
+
+        if self.collapse {
+            let mut html = start_tag;
+            html.push_str(&inner_html);
+            writeln!(&mut html, "</h{}>", rendered_level).unwrap();
+            Event::Html(html.into())
+        } else {
+            self.pending.push_back(Event::Html(start_tag.into()));
+            self.pending.extend(inner_events.into_iter());
+            self.pending.push_back(Event::Html(format!("</h{}>", rendered_level).into()));
+            self.pending.pop_front().unwrap()
+        }
     }
 }
 
@@ -129,6 +502,10 @@ where
     type Item = Event<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(event);
+        }
+
         match self.parser.next() {
             Some(Event::Start(Tag::Heading(level))) => Some(self.convert_heading(level)),
             Some(event) => Some(event),
@@ -150,12 +527,29 @@ mod test {
         buf
     }
 
+    fn convert_autoslug(s: &str) -> String {
+        let mut buf = String::new();
+        let parser = Parser::new(s);
+        let parser = HeadingId::with_autoslug(parser);
+        pulldown_cmark::html::push_html(&mut buf, parser);
+        buf
+    }
+
     #[test]
     fn heading_id() {
         let s = "## Heading {#heading-id}";
         assert_eq!(convert(s).trim_end(), r#"<h2 id="heading-id">Heading</h2>"#);
     }
 
+    #[test]
+    fn explicit_duplicate_ids_pass_through_without_autoslug() {
+        let s = "## Heading {#dup}\n\n## Other {#dup}";
+        let out = convert(s);
+        let mut lines = out.lines();
+        assert_eq!(lines.next().unwrap(), r#"<h2 id="dup">Heading</h2>"#);
+        assert_eq!(lines.next().unwrap(), r#"<h2 id="dup">Other</h2>"#);
+    }
+
     #[test]
     fn normal() {
         let s = "## Heading";
@@ -178,9 +572,11 @@ mod test {
 
     #[test]
     fn whitespace() {
+        // The attribute block is tokenized on whitespace, so only the
+        // `#`-prefixed token becomes the id; bare words are ignored.
         let s = "## ID with space {#id with space}";
         assert_eq!(convert(s).trim_end(),
-            r#"<h2 id="id%20with%20space">ID with space</h2>"#);
+            r#"<h2 id="id">ID with space</h2>"#);
     }
 
     #[test]
@@ -200,4 +596,236 @@ mod test {
         let s = "## ><";
         assert_eq!(convert(s).trim_end(), "<h2>&gt;&lt;</h2>");
     }
+
+    #[test]
+    fn class_attr() {
+        let s = "## Heading {#heading .foo .bar}";
+        assert_eq!(convert(s).trim_end(),
+            r#"<h2 id="heading" class="foo bar">Heading</h2>"#);
+    }
+
+    #[test]
+    fn key_value_attr() {
+        let s = "## Heading {#heading data-toggle=collapse}";
+        assert_eq!(convert(s).trim_end(),
+            r#"<h2 id="heading" data-toggle="collapse">Heading</h2>"#);
+    }
+
+    #[test]
+    fn key_value_attr_escapes_key() {
+        let s = r#"## Heading {#id x"onmouseover="alert(1)}"#;
+        assert_eq!(convert(s).trim_end(),
+            r#"<h2 id="id" x&quot;onmouseover="alert(1)">Heading</h2>"#);
+    }
+
+    #[test]
+    fn class_without_id() {
+        let s = "## Heading {.foo}";
+        assert_eq!(convert(s).trim_end(),
+            r#"<h2 class="foo">Heading</h2>"#);
+    }
+
+    #[test]
+    fn brace_without_attrs() {
+        let s = "## Set {x} to y";
+        assert_eq!(convert(s).trim_end(), "<h2>Set {x} to y</h2>");
+    }
+
+    #[test]
+    fn brace_not_anchored_at_end() {
+        let s = "## Configuring {JSON} Schema Validation";
+        assert_eq!(convert(s).trim_end(),
+            "<h2>Configuring {JSON} Schema Validation</h2>");
+    }
+
+    #[test]
+    fn offset_demotes_heading() {
+        let mut buf = String::new();
+        let parser = Parser::new("# Heading {#heading-id}");
+        let parser = HeadingId::new(parser).with_offset(2);
+        pulldown_cmark::html::push_html(&mut buf, parser);
+        assert_eq!(buf.trim_end(), r#"<h3 id="heading-id">Heading</h3>"#);
+    }
+
+    #[test]
+    fn offset_clamps_to_valid_range() {
+        let mut buf = String::new();
+        let parser = Parser::new("###### Heading");
+        let parser = HeadingId::new(parser).with_offset(3);
+        pulldown_cmark::html::push_html(&mut buf, parser);
+        assert_eq!(buf.trim_end(), "<h6>Heading</h6>");
+    }
+
+    #[test]
+    fn offset_does_not_break_buffering() {
+        // A negative offset must not desync the Start/End matching, which is
+        // keyed off the heading's original level, not the rendered one.
+        let mut buf = String::new();
+        let parser = Parser::new("### Heading\n\nParagraph");
+        let parser = HeadingId::new(parser).with_offset(-2);
+        pulldown_cmark::html::push_html(&mut buf, parser);
+        assert_eq!(buf.trim_end(), "<h1>Heading</h1>\n<p>Paragraph</p>");
+    }
+
+    #[test]
+    fn event_replay_preserves_inner_events() {
+        let s = "# `code` heading {#heading-id}";
+        let parser = Parser::new(s);
+        let parser = HeadingId::new(parser).with_event_replay();
+        let events: Vec<_> = parser.collect();
+
+        assert_eq!(events, vec![
+            Event::Html(r#"<h1 id="heading-id">"#.into()),
+            Event::Code("code".into()),
+            Event::Text(" heading".into()),
+            Event::Html("</h1>".into()),
+        ]);
+    }
+
+    #[test]
+    fn event_replay_renders_same_html_as_collapsing() {
+        let s = "## *Italic* heading {#italic-heading}";
+
+        let mut collapsed = String::new();
+        pulldown_cmark::html::push_html(&mut collapsed,
+            HeadingId::new(Parser::new(s)));
+
+        let mut replayed = String::new();
+        pulldown_cmark::html::push_html(&mut replayed,
+            HeadingId::new(Parser::new(s)).with_event_replay());
+
+        assert_eq!(replayed.trim_end(), collapsed.trim_end());
+    }
+
+    #[test]
+    fn id_after_trailing_code() {
+        let s = "## `code`{#id-after-code}";
+        assert_eq!(convert(s).trim_end(),
+            r#"<h2 id="id-after-code"><code>code</code></h2>"#);
+    }
+
+    #[test]
+    fn id_after_trailing_emphasis() {
+        let s = "## *Italic*{#id-after-emphasis}";
+        assert_eq!(convert(s).trim_end(),
+            r#"<h2 id="id-after-emphasis"><em>Italic</em></h2>"#);
+    }
+
+    #[test]
+    fn id_split_across_text_events() {
+        // `HeadingId` only requires an `Iterator<Item = Event>`, so a
+        // hand-built event stream can exercise a brace block split across
+        // two adjacent `Event::Text`s, which the Markdown parser itself
+        // does not produce.
+        let events = vec![
+            Event::Start(Tag::Heading(2)),
+            Event::Text("Heading ".into()),
+            Event::Text("{#split".into()),
+            Event::Text("}".into()),
+            Event::End(Tag::Heading(2)),
+        ];
+
+        let mut buf = String::new();
+        let parser = HeadingId::new(events.into_iter());
+        pulldown_cmark::html::push_html(&mut buf, parser);
+
+        assert_eq!(buf.trim_end(), r#"<h2 id="split">Heading</h2>"#);
+    }
+
+    #[test]
+    fn toc_collects_headings() {
+        let s = "# Title {#title}\n\n## Section {#section}";
+        let mut buf = String::new();
+        let parser = Parser::new(s);
+        let mut parser = HeadingId::new(parser);
+        pulldown_cmark::html::push_html(&mut buf, &mut parser);
+
+        let toc = parser.toc();
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0], TocEntry { level: 1, id: "title".to_string(), text: "Title".to_string() });
+        assert_eq!(toc[1], TocEntry { level: 2, id: "section".to_string(), text: "Section".to_string() });
+    }
+
+    #[test]
+    fn render_toc_flat() {
+        let s = "## One {#one}\n\n## Two {#two}";
+        let mut buf = String::new();
+        let parser = Parser::new(s);
+        let mut parser = HeadingId::new(parser);
+        pulldown_cmark::html::push_html(&mut buf, &mut parser);
+
+        assert_eq!(render_toc(parser.toc()),
+            "<ul>\n<li><a href=\"#one\">One</a></li>\n<li><a href=\"#two\">Two</a></li>\n</ul>\n");
+    }
+
+    #[test]
+    fn render_toc_nested() {
+        let s = "## Top {#top}\n\n### Child {#child}\n\n## Sibling {#sibling}";
+        let mut buf = String::new();
+        let parser = Parser::new(s);
+        let mut parser = HeadingId::new(parser);
+        pulldown_cmark::html::push_html(&mut buf, &mut parser);
+
+        assert_eq!(render_toc(parser.toc()),
+            "<ul>\n<li><a href=\"#top\">Top</a><ul>\n<li><a href=\"#child\">Child</a></li>\n</ul>\n</li>\n<li><a href=\"#sibling\">Sibling</a></li>\n</ul>\n");
+    }
+
+    #[test]
+    fn render_toc_unwraps_linked_heading() {
+        let s = "### [Link](https://example.com/) {#example}";
+        let mut buf = String::new();
+        let parser = Parser::new(s);
+        let mut parser = HeadingId::new(parser);
+        pulldown_cmark::html::push_html(&mut buf, &mut parser);
+
+        assert_eq!(render_toc(parser.toc()),
+            "<ul>\n<li><a href=\"#example\">Link</a></li>\n</ul>\n");
+    }
+
+    #[test]
+    fn autoslug_basic() {
+        let s = "## Heading Text";
+        assert_eq!(convert_autoslug(s).trim_end(),
+            r#"<h2 id="heading-text">Heading Text</h2>"#);
+    }
+
+    #[test]
+    fn autoslug_explicit_id_wins() {
+        let s = "## Heading {#custom}";
+        assert_eq!(convert_autoslug(s).trim_end(),
+            r#"<h2 id="custom">Heading</h2>"#);
+    }
+
+    #[test]
+    fn autoslug_deduplicates() {
+        let s = "## Heading\n\n## Heading\n\n## Heading";
+        let out = convert_autoslug(s);
+        let mut lines = out.lines();
+        assert_eq!(lines.next().unwrap(), r#"<h2 id="heading">Heading</h2>"#);
+        assert_eq!(lines.next().unwrap(), r#"<h2 id="heading-1">Heading</h2>"#);
+        assert_eq!(lines.next().unwrap(), r#"<h2 id="heading-2">Heading</h2>"#);
+    }
+
+    #[test]
+    fn autoslug_strips_punctuation() {
+        let s = "## Hello, World!";
+        assert_eq!(convert_autoslug(s).trim_end(),
+            r#"<h2 id="hello-world">Hello, World!</h2>"#);
+    }
+
+    #[test]
+    fn autoslug_avoids_explicit_id_collision() {
+        let s = "## Heading {#heading}\n\n## Heading";
+        let out = convert_autoslug(s);
+        let mut lines = out.lines();
+        assert_eq!(lines.next().unwrap(), r#"<h2 id="heading">Heading</h2>"#);
+        assert_eq!(lines.next().unwrap(), r#"<h2 id="heading-1">Heading</h2>"#);
+    }
+
+    #[test]
+    fn autoslug_ignores_class_only_block() {
+        let s = "## Heading {.foo}";
+        assert_eq!(convert_autoslug(s).trim_end(),
+            r#"<h2 id="heading" class="foo">Heading</h2>"#);
+    }
 }